@@ -22,8 +22,24 @@ pub struct Config {
     pub tables: Vec<Table>,
 }
 
+impl Config {
+    // maps a user-facing table name to the real one, e.g. `orgs` -> `org`; unaliased names pass through
+    pub fn resolve_table<'a>(&'a self, name: &'a str) -> &'a str {
+        self.table_alias
+            .iter()
+            .find(|(alias, _)| alias == name)
+            .map(|(_, real)| real.as_str())
+            .unwrap_or(name)
+    }
+
+    pub fn table(&self, name: &str) -> Option<&Table> {
+        self.tables.iter().find(|t| t.name == name)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Table {
+    pub name: String,
     #[serde(flatten)]
     pub columns: IndexMap<String, Column>,
 }
@@ -32,4 +48,31 @@ pub struct Table {
 pub struct Column {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub sanitize: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_table_aliased() {
+        let config = Config { table_alias: vec![("orgs".to_string(), "org".to_string())], tables: vec![] };
+        assert_eq!(config.resolve_table("orgs"), "org");
+    }
+
+    #[test]
+    fn test_resolve_table_passthrough() {
+        let config = Config::default();
+        assert_eq!(config.resolve_table("org"), "org");
+    }
+
+    #[test]
+    fn test_table_lookup() {
+        let config = Config {
+            table_alias: vec![],
+            tables: vec![Table { name: "org".to_string(), columns: IndexMap::new() }],
+        };
+        assert!(config.table("org").is_some());
+        assert!(config.table("missing").is_none());
+    }
 }
\ No newline at end of file
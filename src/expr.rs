@@ -0,0 +1,224 @@
+use crate::parse::{alt, many0, opt, And, Ge, Gt, Identifier, In, Le, Like, LParen, Lt, Ne, Not, Or, Parse, ParseError, RParen, TokenStream};
+use crate::parse::Eq;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Gt,
+    Lt,
+    Ge,
+    Le,
+    In,
+    Like,
+}
+
+impl CompareOp {
+    fn to_sql(&self) -> &'static str {
+        match self {
+            CompareOp::Eq => "=",
+            CompareOp::Ne => "!=",
+            CompareOp::Gt => ">",
+            CompareOp::Lt => "<",
+            CompareOp::Ge => ">=",
+            CompareOp::Le => "<=",
+            CompareOp::In => "in",
+            CompareOp::Like => "like",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Number(String),
+    Identifier(String),
+    // raw SQL fragment, e.g. `now() - interval 1 day`, evaluated by the database rather than treated as data
+    Literal(String),
+}
+
+fn is_boundary(token: &str) -> bool {
+    matches!(token, "," | "/" | ")") || token.eq_ignore_ascii_case("and") || token.eq_ignore_ascii_case("or")
+}
+
+impl<'a> Parse<'a> for Operand {
+    fn parse(input: &mut TokenStream<'a>) -> Result<Self, ParseError> {
+        let mut tt = input.clone();
+        let first = tt.next().ok_or_else(|| ParseError::expected(&tt, "operand"))?;
+        if is_boundary(first) {
+            return Err(ParseError::expected(input, "operand"));
+        }
+
+        let is_number = first.chars().all(|c| c.is_ascii_digit() || c == '.');
+        let is_identifier = first.chars().all(|c| c.is_alphanumeric() || c == '_');
+        let next_is_boundary = tt.peek().map(is_boundary).unwrap_or(true);
+
+        if is_number && next_is_boundary {
+            *input = tt;
+            return Ok(Operand::Number(first.to_string()));
+        }
+        if is_identifier && next_is_boundary {
+            *input = tt;
+            return Ok(Operand::Identifier(first.to_string()));
+        }
+
+        // not a clean single-token number/identifier: capture everything up to the next delimiter
+        // as a raw literal, joining the split tokens back up. A `)` only counts as a boundary at
+        // depth 0 - one that closes a `(` opened within the literal itself (e.g. `now()`) is part
+        // of the literal's text, not the end of it.
+        let mut tt = input.clone();
+        let mut parts = Vec::new();
+        let mut depth = 0i32;
+        while let Some(next) = tt.peek() {
+            if depth == 0 && is_boundary(next) {
+                break;
+            }
+            if next == "(" {
+                depth += 1;
+            } else if next == ")" {
+                depth -= 1;
+            }
+            parts.push(tt.next().unwrap());
+        }
+        if parts.is_empty() {
+            return Err(ParseError::expected(input, "operand"));
+        }
+        *input = tt;
+        Ok(Operand::Literal(parts.concat()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Compare { column: String, op: CompareOp, value: Operand },
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+}
+
+impl Expr {
+    // numeric operands are bound as $n params; identifiers and raw literals are inlined directly
+    pub fn to_sql(&self, next_index: &mut usize, binds: &mut Vec<String>) -> String {
+        match self {
+            Expr::Compare { column, op, value } => {
+                let rendered = match value {
+                    Operand::Number(n) => {
+                        binds.push(n.clone());
+                        let placeholder = format!("${next_index}");
+                        *next_index += 1;
+                        placeholder
+                    }
+                    Operand::Literal(l) => l.clone(),
+                    Operand::Identifier(ident) => format!(r#""{ident}""#),
+                };
+                format!(r#""{column}" {} {rendered}"#, op.to_sql())
+            }
+            Expr::And(lhs, rhs) => format!("({} and {})", lhs.to_sql(next_index, binds), rhs.to_sql(next_index, binds)),
+            Expr::Or(lhs, rhs) => format!("({} or {})", lhs.to_sql(next_index, binds), rhs.to_sql(next_index, binds)),
+            Expr::Not(inner) => format!("not ({})", inner.to_sql(next_index, binds)),
+        }
+    }
+}
+
+fn parse_op(input: &mut TokenStream<'_>) -> Result<CompareOp, ParseError> {
+    alt(input, &[
+        |i| Eq::parse(i).map(|_| CompareOp::Eq),
+        |i| Ne::parse(i).map(|_| CompareOp::Ne),
+        |i| Ge::parse(i).map(|_| CompareOp::Ge),
+        |i| Le::parse(i).map(|_| CompareOp::Le),
+        |i| Gt::parse(i).map(|_| CompareOp::Gt),
+        |i| Lt::parse(i).map(|_| CompareOp::Lt),
+        |i| In::parse(i).map(|_| CompareOp::In),
+        |i| Like::parse(i).map(|_| CompareOp::Like),
+    ])
+}
+
+fn parse_comparison(input: &mut TokenStream<'_>) -> Result<Expr, ParseError> {
+    let mut tt = input.clone();
+    let column = Identifier::parse(&mut tt)?;
+    let op = parse_op(&mut tt)?;
+    let value = Operand::parse(&mut tt)?;
+    *input = tt;
+    Ok(Expr::Compare { column: column.value.to_string(), op, value })
+}
+
+fn parse_primary(input: &mut TokenStream<'_>) -> Result<Expr, ParseError> {
+    if let Some(inner) = opt(input, |i| {
+        Not::parse(i)?;
+        parse_primary(i)
+    }) {
+        return Ok(Expr::Not(Box::new(inner)));
+    }
+
+    if let Some(inner) = opt(input, |i| {
+        LParen::parse(i)?;
+        let inner = parse_or(i)?;
+        RParen::parse(i)?;
+        Ok(inner)
+    }) {
+        return Ok(inner);
+    }
+
+    parse_comparison(input)
+}
+
+fn parse_and(input: &mut TokenStream<'_>) -> Result<Expr, ParseError> {
+    let first = parse_primary(input)?;
+    let (rest, _) = many0(input, |i| {
+        And::parse(i)?;
+        parse_primary(i)
+    });
+    Ok(rest.into_iter().fold(first, |lhs, rhs| Expr::And(Box::new(lhs), Box::new(rhs))))
+}
+
+fn parse_or(input: &mut TokenStream<'_>) -> Result<Expr, ParseError> {
+    let first = parse_and(input)?;
+    let (rest, _) = many0(input, |i| {
+        Or::parse(i)?;
+        parse_and(i)
+    });
+    Ok(rest.into_iter().fold(first, |lhs, rhs| Expr::Or(Box::new(lhs), Box::new(rhs))))
+}
+
+impl<'a> Parse<'a> for Expr {
+    fn parse(input: &mut TokenStream<'a>) -> Result<Self, ParseError> {
+        parse_or(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse::SelectionArgs;
+    use super::*;
+
+    #[test]
+    fn test_parse_comparison() {
+        let args = SelectionArgs::from_shell("created_at gt 5");
+        let expr = Expr::parse(&mut args.token_stream()).unwrap();
+        assert_eq!(
+            expr,
+            Expr::Compare { column: "created_at".to_string(), op: CompareOp::Gt, value: Operand::Number("5".to_string()) }
+        );
+    }
+
+    #[test]
+    fn test_parse_literal_passthrough() {
+        let args = SelectionArgs::from_shell("created_at gt 'now() - interval 1 day'");
+        let expr = Expr::parse(&mut args.token_stream()).unwrap();
+        let Expr::Compare { value: Operand::Literal(literal), .. } = expr else {
+            panic!("expected a literal operand");
+        };
+        assert_eq!(literal, "now() - interval 1 day");
+    }
+
+    #[test]
+    fn test_parse_and_or_precedence() {
+        let args = SelectionArgs::from_shell("a eq 1 and b eq 2 or c eq 3");
+        let expr = Expr::parse(&mut args.token_stream()).unwrap();
+        // `or` binds looser than `and`: (a=1 and b=2) or (c=3)
+        let Expr::Or(lhs, rhs) = expr else {
+            panic!("expected a top-level or");
+        };
+        assert!(matches!(*lhs, Expr::And(..)));
+        assert!(matches!(*rhs, Expr::Compare { .. }));
+    }
+}
@@ -3,9 +3,12 @@
 mod config;
 mod selection;
 mod parse;
+mod expr;
+mod seed;
 
 use std::fs::read;
 use std::mem::take;
+use std::path::PathBuf;
 use anyhow::{anyhow, bail, Result as AnyResult};
 use clap::Parser;
 use env::EnvFile;
@@ -25,6 +28,10 @@ struct Args {
     #[clap(long, short)]
     force: bool,
 
+    #[clap(long)]
+    /// Preview the rows that would be copied without writing to the destination
+    dry_run: bool,
+
     #[clap(long, short)]
     /// Source database URL
     source_url: Option<String>,
@@ -33,6 +40,10 @@ struct Args {
     /// Dest database URL
     dest_url: Option<String>,
 
+    #[clap(long)]
+    /// Path to config.toml (defaults to `~/.config/seed/config.toml`)
+    config: Option<PathBuf>,
+
     args: Vec<String>,
 }
 
@@ -73,10 +84,34 @@ async fn main() -> AnyResult<()> {
     if args.args.is_empty() {
         bail!("No tables selected for seeding");
     }
+    let force = args.force;
+    let dry_run = args.dry_run;
+    let interactive = args.interactive;
+    let config_path = args.config.unwrap_or_else(config::default_config_path);
+    let config = config::read(&config_path).unwrap_or_default();
+
     let args = SelectionArgs::new(args.args);
-    let selections = Punctuated::<Slash, ParseSelection>::parse(&mut args.token_stream())?;
-    let selections: Vec<Selection> = selections.into_vec().into_iter().map(Into::into).collect();
-    dbg!(selections);
+    let mut stream = args.token_stream();
+    let selections = Punctuated::<Slash, ParseSelection>::parse(&mut stream)?;
+    let outer_trailing_error = selections.trailing_error().cloned();
+    let selections = selections.into_vec();
+    if stream.peek().is_some() {
+        // merge in each table's own trailing error (a malformed selector list inside one
+        // table segment, e.g. `org 123,`) so the furthest, most specific failure wins rather
+        // than the outer list's own "expected '/'" at the point where that segment stopped.
+        let err = selections
+            .iter()
+            .filter_map(|s| s.trailing_error().cloned())
+            .fold(outer_trailing_error, |acc, e| Some(match acc {
+                Some(a) => a.merge(e),
+                None => e,
+            }))
+            .unwrap_or_else(|| ParseError::expected(&stream, "',' or '/'"));
+        bail!("{}", err.render(stream.source()));
+    }
+    let selections: Vec<Selection> = selections.into_iter().map(Into::into).collect();
+
+    seed::run(&selections, &mut from_conn, &mut to_conn, &config, &seed::SeedOptions { force, dry_run, interactive }).await?;
     Ok(())
 }
 
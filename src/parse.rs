@@ -1,34 +1,105 @@
 use std::borrow::Cow;
 
-#[derive(Debug)]
+// a token's location in the original joined argument string, used to anchor diagnostics
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub offset: usize,
+    pub len: usize,
+}
+
+#[derive(Debug, Clone)]
 pub struct ParseError {
-    message: Cow<'static, str>,
+    position: usize,
+    len: usize,
+    expected: Vec<Cow<'static, str>>,
 }
 
 impl std::error::Error for ParseError {}
 
 impl std::fmt::Display for ParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "parse error: {}", self.message)
+        let mut expected: Vec<&str> = self.expected.iter().map(|s| s.as_ref()).collect();
+        expected.sort_unstable();
+        expected.dedup();
+        write!(f, "parse error at byte {}: expected one of: {}", self.position, expected.join(", "))
+    }
+}
+
+impl ParseError {
+    // position-less error (byte 0) for call sites with no stream handy; loses merge() against any error past
+    // byte 0, and against one at byte 0 their expected sets are combined
+    pub fn new(message: impl Into<Cow<'static, str>>) -> Self {
+        Self { position: 0, len: 0, expected: vec![message.into()] }
+    }
+
+    pub fn expected(input: &TokenStream<'_>, what: impl Into<Cow<'static, str>>) -> Self {
+        let (position, len) = input.position();
+        Self { position, len: len.max(1), expected: vec![what.into()] }
+    }
+
+    // keeps whichever error reached furthest into the input; furthest failure is usually the most useful to report
+    pub fn merge(self, other: Self) -> Self {
+        match self.position.cmp(&other.position) {
+            std::cmp::Ordering::Less => other,
+            std::cmp::Ordering::Greater => self,
+            std::cmp::Ordering::Equal => {
+                let mut expected = self.expected;
+                for e in other.expected {
+                    if !expected.contains(&e) {
+                        expected.push(e);
+                    }
+                }
+                Self { position: self.position, len: self.len.max(other.len), expected }
+            }
+        }
+    }
+
+    // header line plus the offending slice of `source` with a caret underline beneath it
+    pub fn render(&self, source: &str) -> String {
+        let mut expected: Vec<&str> = self.expected.iter().map(|s| s.as_ref()).collect();
+        expected.sort_unstable();
+        expected.dedup();
+        let header = format!("expected one of: {}", expected.join(", "));
+
+        let position = self.position.min(source.len());
+        let line_start = source[..position].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line_end = source[position..].find('\n').map(|i| position + i).unwrap_or(source.len());
+        let line = &source[line_start..line_end];
+        let column = position - line_start;
+        format!("{header}\n{line}\n{}{}", " ".repeat(column), "^".repeat(self.len.max(1)))
     }
 }
 
 #[derive(Debug)]
 pub struct TokenStream<'a> {
     tokens: &'a [&'a str],
+    spans: Vec<Span>,
+    source: &'a str,
 }
 
 impl<'a> TokenStream<'a> {
+    // synthesizes sequential spans as if tokens were space-separated; for tests, real parsing goes through SelectionArgs
+    pub fn new(tokens: &'a [&'a str]) -> Self {
+        let mut spans = Vec::with_capacity(tokens.len());
+        let mut offset = 0;
+        for t in tokens {
+            spans.push(Span { offset, len: t.len() });
+            offset += t.len() + 1;
+        }
+        Self { tokens, spans, source: "" }
+    }
+
+    pub fn with_spans(tokens: &'a [&'a str], spans: Vec<Span>, source: &'a str) -> Self {
+        debug_assert_eq!(tokens.len(), spans.len());
+        Self { tokens, spans, source }
+    }
+
     pub fn into_vec(self) -> Vec<&'a str> {
         self.tokens.to_vec()
     }
 
     pub fn clone(&self) -> Self {
-        Self { tokens: self.tokens }
-    }
-
-    pub fn new(tokens: &'a [&'a str]) -> Self {
-        Self { tokens }
+        Self { tokens: self.tokens, spans: self.spans.clone(), source: self.source }
     }
 
     pub fn next(&mut self) -> Option<&'a str> {
@@ -37,14 +108,12 @@ impl<'a> TokenStream<'a> {
         }
         let t = self.tokens[0];
         self.tokens = &self.tokens[1..];
+        self.spans.remove(0);
         Some(t)
     }
 
     pub fn peek(&self) -> Option<&'a str> {
-        if self.tokens.is_empty() {
-            return None;
-        }
-        Some(self.tokens[0])
+        self.tokens.first().copied()
     }
 
     pub fn next_if(&mut self, predicate: impl Fn(&str) -> bool) -> Option<&'a str> {
@@ -54,16 +123,20 @@ impl<'a> TokenStream<'a> {
         let t = self.tokens[0];
         if predicate(t) {
             self.tokens = &self.tokens[1..];
+            self.spans.remove(0);
             Some(t)
         } else {
             None
         }
     }
-}
 
-impl ParseError {
-    pub fn new(message: impl Into<Cow<'static, str>>) -> Self {
-        Self { message: message.into() }
+    // offset/length of the next unconsumed token, or (source.len(), 0) at end-of-input
+    pub fn position(&self) -> (usize, usize) {
+        self.spans.first().map(|s| (s.offset, s.len)).unwrap_or((self.source.len(), 0))
+    }
+
+    pub fn source(&self) -> &'a str {
+        self.source
     }
 }
 
@@ -71,37 +144,149 @@ pub trait Parse<'a>: Sized {
     fn parse(input: &mut TokenStream<'a>) -> Result<Self, ParseError>;
 }
 
+// tries each parser in turn, committing to the first success; on total failure, merges the errors
+// so the caller sees whichever alternative got furthest before giving up
+pub fn alt<'a, T>(
+    input: &mut TokenStream<'a>,
+    parsers: &[fn(&mut TokenStream<'a>) -> Result<T, ParseError>],
+) -> Result<T, ParseError> {
+    let mut furthest: Option<ParseError> = None;
+    for parser in parsers {
+        let mut tt = input.clone();
+        match parser(&mut tt) {
+            Ok(t) => {
+                *input = tt;
+                return Ok(t);
+            }
+            Err(e) => furthest = Some(match furthest {
+                Some(f) => f.merge(e),
+                None => e,
+            }),
+        }
+    }
+    Err(furthest.unwrap_or_else(|| ParseError::expected(input, "alternative")))
+}
+
+// not matching isn't an error, so the failure is discarded rather than propagated
+pub fn opt<'a, T>(
+    input: &mut TokenStream<'a>,
+    parser: impl FnOnce(&mut TokenStream<'a>) -> Result<T, ParseError>,
+) -> Option<T> {
+    let mut tt = input.clone();
+    match parser(&mut tt) {
+        Ok(t) => {
+            *input = tt;
+            Some(t)
+        }
+        Err(_) => None,
+    }
+}
+
+// zero matches is fine; also returns the error that ended the loop, see Punctuated::trailing_error
+pub fn many0<'a, T>(
+    input: &mut TokenStream<'a>,
+    parser: impl Fn(&mut TokenStream<'a>) -> Result<T, ParseError>,
+) -> (Vec<T>, Option<ParseError>) {
+    let mut items = Vec::new();
+    loop {
+        let mut tt = input.clone();
+        match parser(&mut tt) {
+            Ok(t) => {
+                *input = tt;
+                items.push(t);
+            }
+            Err(e) => return (items, Some(e)),
+        }
+    }
+}
+
+// like many0, but fails if parser never matches
+pub fn many1<'a, T>(
+    input: &mut TokenStream<'a>,
+    parser: impl Fn(&mut TokenStream<'a>) -> Result<T, ParseError>,
+) -> Result<Vec<T>, ParseError> {
+    let (items, trailing) = many0(input, parser);
+    if items.is_empty() {
+        Err(trailing.unwrap_or_else(|| ParseError::expected(input, "at least one item")))
+    } else {
+        Ok(items)
+    }
+}
+
+// a failed sep only ends the list cleanly when input is genuinely exhausted; otherwise it's an error,
+// not a cue to try another item at the same position
+pub fn separated_list<'a, S, T>(
+    input: &mut TokenStream<'a>,
+    sep: impl Fn(&mut TokenStream<'a>) -> Result<S, ParseError>,
+    item: impl Fn(&mut TokenStream<'a>) -> Result<T, ParseError>,
+) -> (Vec<T>, Option<ParseError>) {
+    let mut items = Vec::new();
+    let mut tt = input.clone();
+    match item(&mut tt) {
+        Ok(t) => {
+            *input = tt;
+            items.push(t);
+        }
+        Err(e) => return (items, Some(e)),
+    }
+    loop {
+        // sep and item are tried against the same clone and committed together: if item then
+        // fails, the separator must not be consumed either, or a trailing `,`/`/` would vanish
+        // from `input` and leave the stream looking fully consumed instead of malformed.
+        let mut tt = input.clone();
+        if let Err(e) = sep(&mut tt) {
+            return (items, if input.peek().is_none() { None } else { Some(e) });
+        }
+        match item(&mut tt) {
+            Ok(t) => {
+                *input = tt;
+                items.push(t);
+            }
+            Err(e) => return (items, Some(e)),
+        }
+    }
+}
+
+pub fn keyword<'a>(input: &mut TokenStream<'a>, kw: &'static str) -> Result<&'a str, ParseError> {
+    let mut tt = input.clone();
+    match tt.next() {
+        Some(t) if t.eq_ignore_ascii_case(kw) => {
+            *input = tt;
+            Ok(t)
+        }
+        _ => Err(ParseError::expected(input, format!("'{kw}'"))),
+    }
+}
+
 pub struct Punctuated<P, T> {
     delimiter: std::marker::PhantomData<P>,
     inner: Vec<T>,
+    trailing_error: Option<ParseError>,
 }
 
 
 impl<P, T> Punctuated<P, T> {
     pub fn new(inner: Vec<T>) -> Self {
-        Self { inner, delimiter: std::marker::PhantomData }
+        Self { inner, delimiter: std::marker::PhantomData, trailing_error: None }
     }
 
     pub fn into_vec(self) -> Vec<T> {
         self.inner
     }
+
+    // error from the last failed element attempt, if any; Punctuated itself never fails (zero elements is valid)
+    pub fn trailing_error(&self) -> Option<&ParseError> {
+        self.trailing_error.as_ref()
+    }
 }
 
 impl<'a, P: Parse<'a>, T: Parse<'a>> Parse<'a> for Punctuated<P, T> {
     fn parse(input: &mut TokenStream<'a>) -> Result<Self, ParseError> {
         let mut tt = input.clone();
-        let _ignore = P::parse(&mut tt);
-        let mut inner = Vec::new();
-        loop {
-            let t = T::parse(&mut tt);
-            match t {
-                Ok(t) => inner.push(t),
-                Err(_) => break,
-            }
-            let _ignore = P::parse(&mut tt);
-        }
+        opt(&mut tt, P::parse);
+        let (inner, trailing_error) = separated_list(&mut tt, P::parse, T::parse);
         *input = tt;
-        Ok(Self::new(inner))
+        Ok(Self { inner, delimiter: std::marker::PhantomData, trailing_error })
     }
 }
 
@@ -109,16 +294,7 @@ pub struct Comma;
 
 impl<'a> Parse<'a> for Comma {
     fn parse(input: &mut TokenStream<'a>) -> Result<Self, ParseError> {
-        let mut tt = input.clone();
-        let Some(t) = tt.next() else {
-            return Err(ParseError::new("expected comma, got nothing"));
-        };
-        if t == "," {
-            *input = tt;
-            Ok(Self)
-        } else {
-            Err(ParseError::new("expected comma, got something else"))
-        }
+        keyword(input, ",").map(|_| Self)
     }
 }
 
@@ -126,16 +302,7 @@ pub struct Period;
 
 impl<'a> Parse<'a> for Period {
     fn parse(input: &mut TokenStream<'a>) -> Result<Self, ParseError> {
-        let mut tt = input.clone();
-        let Some(t) = tt.next() else {
-            return Err(ParseError::new("expected comma, got nothing"));
-        };
-        if t == "." {
-            *input = tt;
-            Ok(Self)
-        } else {
-            Err(ParseError::new("expected comma, got something else"))
-        }
+        keyword(input, ".").map(|_| Self)
     }
 }
 
@@ -143,16 +310,7 @@ pub struct Slash;
 
 impl<'a> Parse<'a> for Slash {
     fn parse(input: &mut TokenStream<'a>) -> Result<Self, ParseError> {
-        let mut tt = input.clone();
-        let Some(t) = tt.next() else {
-            return Err(ParseError::new("expected comma, got nothing"));
-        };
-        if t == "/" {
-            *input = tt;
-            Ok(Self)
-        } else {
-            Err(ParseError::new("expected comma, got something else"))
-        }
+        keyword(input, "/").map(|_| Self)
     }
 }
 
@@ -160,16 +318,7 @@ pub struct Gt;
 
 impl<'a> Parse<'a> for Gt {
     fn parse(input: &mut TokenStream<'a>) -> Result<Self, ParseError> {
-        let mut tt = input.clone();
-        let Some(t) = tt.next() else {
-            return Err(ParseError::new("expected >, got nothing"));
-        };
-        if t == ">" || t == "gt" {
-            *input = tt;
-            Ok(Self)
-        } else {
-            Err(ParseError::new("expected >, got something else"))
-        }
+        alt(input, &[|i| keyword(i, ">").map(|_| Self), |i| keyword(i, "gt").map(|_| Self)])
     }
 }
 
@@ -177,16 +326,95 @@ pub struct Lt;
 
 impl<'a> Parse<'a> for Lt {
     fn parse(input: &mut TokenStream<'a>) -> Result<Self, ParseError> {
-        let mut tt = input.clone();
-        let Some(t) = tt.next() else {
-            return Err(ParseError::new("expected <, got nothing"));
-        };
-        if t == "<" || t == "lt" {
-            *input = tt;
-            Ok(Self)
-        } else {
-            Err(ParseError::new("expected <, got something else"))
-        }
+        alt(input, &[|i| keyword(i, "<").map(|_| Self), |i| keyword(i, "lt").map(|_| Self)])
+    }
+}
+
+pub struct Eq;
+
+impl<'a> Parse<'a> for Eq {
+    fn parse(input: &mut TokenStream<'a>) -> Result<Self, ParseError> {
+        alt(input, &[|i| keyword(i, "=").map(|_| Self), |i| keyword(i, "eq").map(|_| Self)])
+    }
+}
+
+pub struct Ne;
+
+impl<'a> Parse<'a> for Ne {
+    fn parse(input: &mut TokenStream<'a>) -> Result<Self, ParseError> {
+        alt(input, &[|i| keyword(i, "!=").map(|_| Self), |i| keyword(i, "ne").map(|_| Self)])
+    }
+}
+
+pub struct Ge;
+
+impl<'a> Parse<'a> for Ge {
+    fn parse(input: &mut TokenStream<'a>) -> Result<Self, ParseError> {
+        alt(input, &[|i| keyword(i, ">=").map(|_| Self), |i| keyword(i, "ge").map(|_| Self)])
+    }
+}
+
+pub struct Le;
+
+impl<'a> Parse<'a> for Le {
+    fn parse(input: &mut TokenStream<'a>) -> Result<Self, ParseError> {
+        alt(input, &[|i| keyword(i, "<=").map(|_| Self), |i| keyword(i, "le").map(|_| Self)])
+    }
+}
+
+pub struct In;
+
+impl<'a> Parse<'a> for In {
+    fn parse(input: &mut TokenStream<'a>) -> Result<Self, ParseError> {
+        keyword(input, "in").map(|_| Self)
+    }
+}
+
+pub struct Like;
+
+impl<'a> Parse<'a> for Like {
+    fn parse(input: &mut TokenStream<'a>) -> Result<Self, ParseError> {
+        keyword(input, "like").map(|_| Self)
+    }
+}
+
+pub struct And;
+
+impl<'a> Parse<'a> for And {
+    fn parse(input: &mut TokenStream<'a>) -> Result<Self, ParseError> {
+        alt(input, &[|i| keyword(i, "and").map(|_| Self), |i| keyword(i, "&&").map(|_| Self)])
+    }
+}
+
+pub struct Or;
+
+impl<'a> Parse<'a> for Or {
+    fn parse(input: &mut TokenStream<'a>) -> Result<Self, ParseError> {
+        alt(input, &[|i| keyword(i, "or").map(|_| Self), |i| keyword(i, "||").map(|_| Self)])
+    }
+}
+
+pub struct Not;
+
+impl<'a> Parse<'a> for Not {
+    fn parse(input: &mut TokenStream<'a>) -> Result<Self, ParseError> {
+        alt(input, &[|i| keyword(i, "not").map(|_| Self), |i| keyword(i, "!").map(|_| Self)])
+    }
+}
+
+pub struct LParen;
+
+impl<'a> Parse<'a> for LParen {
+    fn parse(input: &mut TokenStream<'a>) -> Result<Self, ParseError> {
+        keyword(input, "(").map(|_| Self)
+    }
+}
+
+pub struct RParen;
+
+impl<'a> Parse<'a> for RParen {
+    fn parse(input: &mut TokenStream<'a>) -> Result<Self, ParseError> {
+        keyword(input, ")").map(|_| Self)
     }
 }
 
@@ -202,65 +430,70 @@ pub struct Literal<'a> {
 
 pub struct Sequence<T> {
     inner: Vec<T>,
+    trailing_error: Option<ParseError>,
 }
 
 pub struct Identifier<'a> {
-    value: &'a str,
+    pub(crate) value: &'a str,
 }
 
 impl<'a> Parse<'a> for Identifier<'a> {
     fn parse(input: &mut TokenStream<'a>) -> Result<Self, ParseError> {
         let mut tt = input.clone();
-        let Some(t) = tt.next() else {
-            return Err(ParseError::new("expected identifier, got nothing"));
-        };
-        if t.chars().all(|c| c.is_alphanumeric() || c == '_') {
-            *input = tt;
-            Ok(Self { value: t })
-        } else {
-            Err(ParseError::new("expected identifier, got something else"))
+        match tt.next() {
+            Some(t) if !t.is_empty() && t.chars().all(|c| c.is_alphanumeric() || c == '_') => {
+                *input = tt;
+                Ok(Self { value: t })
+            }
+            _ => Err(ParseError::expected(input, "identifier")),
         }
     }
 }
 
 impl<'a, T: Parse<'a>> Parse<'a> for Sequence<T> {
     fn parse(input: &mut TokenStream<'a>) -> Result<Self, ParseError> {
-        let mut inner = Vec::new();
-        loop {
-            let t = T::parse(input);
-            match t {
-                Ok(t) => inner.push(t),
-                Err(_) => break,
-            }
-        }
-        Ok(Self { inner })
+        let (inner, trailing_error) = many0(input, T::parse);
+        Ok(Self { inner, trailing_error })
     }
 }
 
 
-pub fn lex(input: &[String]) -> Vec<&str> {
-    let mut r = Vec::new();
-    for input in input {
+// splits input (joined with a single space) into tokens on `( ) , /`, tracking each token's byte
+// offset into the joined string so errors can point back at it
+pub fn lex(input: &[String]) -> (Vec<&str>, Vec<Span>) {
+    let mut tokens = Vec::new();
+    let mut spans = Vec::new();
+    let mut cursor = 0;
+    for (w, word) in input.iter().enumerate() {
+        if w > 0 {
+            cursor += 1; // the space that joins this word to the previous one
+        }
         let mut last = 0;
-        for (i, ch) in input.char_indices() {
+        for (i, ch) in word.char_indices() {
             if ch == '(' || ch == ')' || ch == ',' || ch == '/' {
                 if last != i {
-                    r.push(&input[last..i]);
+                    tokens.push(&word[last..i]);
+                    spans.push(Span { offset: cursor + last, len: i - last });
                 }
-                r.push(&input[i..i + 1]);
+                tokens.push(&word[i..i + 1]);
+                spans.push(Span { offset: cursor + i, len: ch.len_utf8() });
                 last = i + ch.len_utf8();
             }
         }
-        if last < input.len() {
-            r.push(&input[last..]);
+        if last < word.len() {
+            tokens.push(&word[last..]);
+            spans.push(Span { offset: cursor + last, len: word.len() - last });
         }
+        cursor += word.len();
     }
-    r
+    (tokens, spans)
 }
 
 pub struct SelectionArgs {
     args: Vec<String>,
+    source: String,
     tokens: Vec<&'static str>,
+    spans: Vec<Span>,
 }
 
 impl SelectionArgs {
@@ -271,13 +504,14 @@ impl SelectionArgs {
     }
 
     pub fn new(args: Vec<String>) -> SelectionArgs {
-        let tokens = lex(&args);
-        let tokens = unsafe { std::mem::transmute(tokens) };
-        Self { args, tokens }
+        let source = args.join(" ");
+        let (tokens, spans) = lex(&args);
+        let tokens: Vec<&'static str> = unsafe { std::mem::transmute(tokens) };
+        Self { args, source, tokens, spans }
     }
 
     pub fn token_stream(&self) -> TokenStream<'_> {
-        TokenStream::new(&self.tokens)
+        TokenStream::with_spans(&self.tokens, self.spans.clone(), &self.source)
     }
 }
 
@@ -310,8 +544,24 @@ mod tests {
         let tt = args.token_stream().into_vec();
         assert_eq!(tt, ["foo", "(", "bar", ",", "baz", ")"]);
     }
-}
-
-
 
+    #[test]
+    fn test_error_position_picks_furthest() {
+        let args = SelectionArgs::new(vec!["org".to_string(), "123".to_string(), "/".to_string()]);
+        let mut stream = args.token_stream();
+        let selections = Punctuated::<Slash, crate::selection::ParseSelection>::parse(&mut stream).unwrap();
+        // the trailing `/` starts a second selection with no table name, which is the furthest
+        // point parsing reached before giving up.
+        assert!(selections.trailing_error().is_some());
+        // "org 123 /" is 9 bytes; the error sits at end-of-input, past the `/` at byte 8
+        assert_eq!(selections.trailing_error().unwrap().position, 9);
+    }
 
+    #[test]
+    fn test_error_render_has_caret() {
+        let err = ParseError::new("placeholder").merge(ParseError { position: 4, len: 2, expected: vec!["','".into(), "'/'".into()] });
+        let rendered = err.render("org 12 bogus");
+        assert!(rendered.contains("expected one of: ',', '/'"));
+        assert!(rendered.contains("^^"));
+    }
+}
@@ -0,0 +1,486 @@
+use anyhow::{bail, Context, Result as AnyResult};
+use sqlx::postgres::PgRow;
+use sqlx::{Connection, PgConnection, Postgres, Row, Transaction};
+
+use crate::config::{Config, Table};
+use crate::selection::{Selection, Selector};
+
+// one column of a table, as reported by information_schema.columns; we select every column cast
+// to text and cast the bound parameter back to data_type on insert, so the engine never has to
+// know how to decode/encode any particular Postgres type
+struct ColumnInfo {
+    name: String,
+    data_type: String,
+}
+
+// rows copied for one step of the `/`-separated chain, kept around so a later step can filter on
+// the primary keys collected here (see Selector::ForeignKey)
+struct CopiedSelection {
+    table: String,
+    ids: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Bind {
+    Scalar(String),
+    Array(Vec<String>),
+}
+
+pub struct SeedOptions {
+    pub force: bool,
+    // preview rows instead of writing them: to_conn is never touched
+    pub dry_run: bool,
+    // show the preview and ask for confirmation before each selection is copied
+    pub interactive: bool,
+}
+
+pub async fn run(
+    selections: &[Selection],
+    from_conn: &mut PgConnection,
+    to_conn: &mut PgConnection,
+    config: &Config,
+    options: &SeedOptions,
+) -> AnyResult<()> {
+    let mut tx = if options.dry_run { None } else { Some(to_conn.begin().await?) };
+    let mut copied: Vec<CopiedSelection> = Vec::new();
+
+    for (i, selection) in selections.iter().enumerate() {
+        let table = config.resolve_table(&selection.table).to_string();
+        let table_config = config.table(&table);
+        let columns = table_columns(from_conn, &table)
+            .await
+            .with_context(|| format!("loading columns for `{table}`"))?;
+        if columns.is_empty() {
+            bail!("table `{table}` has no columns (does it exist?)");
+        }
+
+        let mut selectors = selection.selectors.clone();
+        let has_explicit_id = selectors.iter().any(|s| matches!(s, Selector::Id(_)));
+        let via_foreign_key = i > 0 && !has_explicit_id;
+        if via_foreign_key {
+            selectors.push(Selector::ForeignKey(i - 1));
+        }
+        if options.interactive {
+            selectors.push(Selector::Prompt);
+        }
+        let prompt = selectors.iter().any(|s| matches!(s, Selector::Prompt));
+
+        let rows = select_rows(from_conn, &table, &columns, table_config, &selectors, &copied).await?;
+
+        if options.dry_run || prompt {
+            let from_table = via_foreign_key.then(|| copied[i - 1].table.as_str());
+            print!("{}", render_preview(&table, &columns, &rows, from_table));
+        }
+        if prompt && !confirm(&format!("copy {} row(s) into `{table}`?", rows.len()))? {
+            bail!("aborted by user at `{table}`");
+        }
+
+        let mut ids = Vec::with_capacity(rows.len());
+        if let Some(tx) = tx.as_mut() {
+            if options.force {
+                sqlx::query(&format!(r#"truncate table "{table}" cascade"#)).execute(&mut **tx).await?;
+            }
+            for row in &rows {
+                insert_row(tx, &table, &columns, row, options.force).await?;
+            }
+        }
+        for row in &rows {
+            if let Ok(id) = row.try_get::<String, _>("id") {
+                ids.push(id);
+            }
+        }
+        copied.push(CopiedSelection { table, ids });
+    }
+
+    if let Some(tx) = tx {
+        tx.commit().await?;
+    }
+    Ok(())
+}
+
+async fn select_rows(
+    conn: &mut PgConnection,
+    table: &str,
+    columns: &[ColumnInfo],
+    table_config: Option<&Table>,
+    selectors: &[Selector],
+    copied: &[CopiedSelection],
+) -> AnyResult<Vec<PgRow>> {
+    let select_list: Vec<String> = columns
+        .iter()
+        .map(|c| {
+            let projection = table_config
+                .and_then(|t| t.columns.get(&c.name))
+                .and_then(|column| column.sanitize.as_deref())
+                .map(|sanitize| sanitize_expr(&c.name, sanitize))
+                .unwrap_or_else(|| format!(r#""{}""#, c.name));
+            format!(r#"({})::text as "{}""#, projection, c.name)
+        })
+        .collect();
+
+    // Resolve anything that needs `conn` up front (a `ForeignKey` selector's column name), so the
+    // actual WHERE/ORDER BY/LIMIT assembly in `build_where_order_limit` is pure and testable
+    // without a live connection.
+    let mut resolved_foreign_keys = Vec::with_capacity(selectors.len());
+    for selector in selectors {
+        resolved_foreign_keys.push(match selector {
+            Selector::ForeignKey(idx) => {
+                let parent = copied
+                    .get(*idx)
+                    .ok_or_else(|| anyhow::anyhow!("foreign key selector referenced an unknown prior selection"))?;
+                let (fk_column, _referenced_column) = foreign_key(conn, table, &parent.table).await?;
+                Some((fk_column, parent.ids.clone()))
+            }
+            _ => None,
+        });
+    }
+
+    let (conditions, order_by, limit, binds) = build_where_order_limit(table, columns, selectors, &resolved_foreign_keys)?;
+
+    let mut sql = format!(r#"select {} from "{}""#, select_list.join(", "), table);
+    if !conditions.is_empty() {
+        sql.push_str(" where ");
+        sql.push_str(&conditions.join(" and "));
+    }
+    if let Some(order_by) = order_by {
+        sql.push_str(&format!(" order by {order_by}"));
+    }
+    if let Some(limit) = limit {
+        sql.push_str(&format!(" limit {limit}"));
+    }
+
+    let mut query = sqlx::query(&sql);
+    for bind in binds {
+        query = match bind {
+            Bind::Scalar(s) => query.bind(s),
+            Bind::Array(a) => query.bind(a),
+        };
+    }
+    Ok(query.fetch_all(conn).await?)
+}
+
+// builds the WHERE/ORDER BY/LIMIT fragments (and their bind values) from `selectors`, given any
+// foreign-key columns already resolved against the connection (see `select_rows`)
+fn build_where_order_limit(
+    table: &str,
+    columns: &[ColumnInfo],
+    selectors: &[Selector],
+    resolved_foreign_keys: &[Option<(String, Vec<String>)>],
+) -> AnyResult<(Vec<String>, Option<String>, Option<usize>, Vec<Bind>)> {
+    let mut conditions = Vec::new();
+    let mut order_by = None;
+    let mut limit = None;
+    let mut binds: Vec<Bind> = Vec::new();
+
+    for (selector, resolved_fk) in selectors.iter().zip(resolved_foreign_keys) {
+        match selector {
+            Selector::Id(id) => {
+                binds.push(Bind::Scalar(id.clone()));
+                conditions.push(format!(r#""id"::text = ${}"#, binds.len()));
+            }
+            Selector::Rand(n) => {
+                order_by = Some("random()".to_string());
+                limit = Some(*n);
+            }
+            Selector::Latest(n) => {
+                order_by = Some(r#""created_at" desc"#.to_string());
+                limit = Some(*n);
+            }
+            Selector::Limit(n) => {
+                limit = Some(*n);
+            }
+            Selector::Sort(column, desc) => {
+                if !columns.iter().any(|c| c.name == *column) {
+                    bail!("`{column}` is not a column of `{table}`");
+                }
+                order_by = Some(format!(r#""{}" {}"#, column, if *desc { "desc" } else { "asc" }));
+            }
+            Selector::Expr(expr) => {
+                let mut next_index = binds.len() + 1;
+                let mut values = Vec::new();
+                conditions.push(expr.to_sql(&mut next_index, &mut values));
+                binds.extend(values.into_iter().map(Bind::Scalar));
+            }
+            // Doesn't affect the query; `run` checks for it directly to decide whether to
+            // show the preview and ask for confirmation before copying this selection.
+            Selector::Prompt => {}
+            Selector::ForeignKey(_) => {
+                let (fk_column, parent_ids) = resolved_fk
+                    .as_ref()
+                    .ok_or_else(|| anyhow::anyhow!("foreign key selector's column was not resolved"))?;
+                if parent_ids.is_empty() {
+                    conditions.push("false".to_string());
+                } else {
+                    binds.push(Bind::Array(parent_ids.clone()));
+                    conditions.push(format!(r#""{}"::text = any(${})"#, fk_column, binds.len()));
+                }
+            }
+        }
+    }
+    Ok((conditions, order_by, limit, binds))
+}
+
+async fn insert_row(
+    tx: &mut Transaction<'_, Postgres>,
+    table: &str,
+    columns: &[ColumnInfo],
+    row: &PgRow,
+    force: bool,
+) -> AnyResult<()> {
+    let column_list: Vec<String> = columns.iter().map(|c| format!(r#""{}""#, c.name)).collect();
+    let placeholders: Vec<String> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, c)| format!("${}::{}", i + 1, c.data_type))
+        .collect();
+
+    let conflict_clause = if force {
+        String::new()
+    } else if let Some(id_column) = columns.iter().find(|c| c.name == "id") {
+        let updates: Vec<String> = columns
+            .iter()
+            .filter(|c| c.name != id_column.name)
+            .map(|c| format!(r#""{0}" = excluded."{0}""#, c.name))
+            .collect();
+        if updates.is_empty() {
+            format!(r#" on conflict ("{}") do nothing"#, id_column.name)
+        } else {
+            format!(r#" on conflict ("{}") do update set {}"#, id_column.name, updates.join(", "))
+        }
+    } else {
+        String::new()
+    };
+
+    let sql = format!(
+        r#"insert into "{}" ({}) values ({}){}"#,
+        table,
+        column_list.join(", "),
+        placeholders.join(", "),
+        conflict_clause,
+    );
+
+    let mut query = sqlx::query(&sql);
+    for column in columns {
+        let value: Option<String> = row.try_get(column.name.as_str())?;
+        query = query.bind(value);
+    }
+    query.execute(&mut **tx).await?;
+    Ok(())
+}
+
+fn render_preview(table: &str, columns: &[ColumnInfo], rows: &[PgRow], via_foreign_key_from: Option<&str>) -> String {
+    let summary = match via_foreign_key_from {
+        Some(parent) => format!("{table}: {} rows via foreign key from `{parent}`", rows.len()),
+        None => format!("{table}: {} rows", rows.len()),
+    };
+
+    let headers: Vec<&str> = columns.iter().map(|c| c.name.as_str()).collect();
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            columns
+                .iter()
+                .map(|c| row.try_get::<Option<String>, _>(c.name.as_str()).ok().flatten().unwrap_or_else(|| "NULL".to_string()))
+                .collect()
+        })
+        .collect();
+
+    render_table(&summary, &headers, &cells)
+}
+
+// split out of render_preview so the column-width/alignment logic can be tested without a live PgRow
+fn render_table(summary: &str, headers: &[&str], cells: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+    for row in cells {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(cell.len());
+        }
+    }
+
+    let mut out = format!(
+        "{summary}\n{}\n{}\n",
+        render_row(&widths, headers),
+        widths.iter().map(|w| "-".repeat(*w)).collect::<Vec<_>>().join("-+-"),
+    );
+    for row in cells {
+        out.push_str(&render_row(&widths, row));
+        out.push('\n');
+    }
+    out
+}
+
+fn render_row(widths: &[usize], cells: &[impl AsRef<str>]) -> String {
+    cells.iter().zip(widths).map(|(c, w)| format!("{:<width$}", c.as_ref(), width = *w)).collect::<Vec<_>>().join(" | ")
+}
+
+fn confirm(prompt: &str) -> AnyResult<bool> {
+    use std::io::Write;
+    print!("{prompt} [y/N] ");
+    std::io::stdout().flush()?;
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    Ok(line.trim().eq_ignore_ascii_case("y"))
+}
+
+// a handful of sanitize names are built-in shorthand; anything else is assumed to already be a
+// SQL expression (e.g. `md5(email)`, `'redacted'`) and passed through verbatim
+fn sanitize_expr(column: &str, sanitize: &str) -> String {
+    match sanitize {
+        "null" => "null".to_string(),
+        "hash" => format!(r#"md5("{column}"::text)"#),
+        "fake_email" => format!(r#"'user_' || "{column}"::text || '@example.com'"#),
+        expr => expr.to_string(),
+    }
+}
+
+async fn table_columns(conn: &mut PgConnection, table: &str) -> AnyResult<Vec<ColumnInfo>> {
+    let rows = sqlx::query(
+        r#"
+        select column_name, data_type
+        from information_schema.columns
+        where table_name = $1
+        order by ordinal_position
+        "#,
+    )
+    .bind(table)
+    .fetch_all(conn)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| ColumnInfo { name: row.get("column_name"), data_type: row.get("data_type") })
+        .collect())
+}
+
+// resolves the foreign key column on child_table that references parent_table, e.g.
+// foreign_key(conn, "deduction", "org") returns ("org_id", "id")
+async fn foreign_key(
+    conn: &mut PgConnection,
+    child_table: &str,
+    parent_table: &str,
+) -> AnyResult<(String, String)> {
+    let row = sqlx::query(
+        r#"
+        select kcu.column_name as fk_column, ccu.column_name as referenced_column
+        from information_schema.table_constraints tc
+        join information_schema.key_column_usage kcu
+            on tc.constraint_name = kcu.constraint_name and tc.table_schema = kcu.table_schema
+        join information_schema.constraint_column_usage ccu
+            on tc.constraint_name = ccu.constraint_name and tc.table_schema = ccu.table_schema
+        where tc.constraint_type = 'FOREIGN KEY'
+            and tc.table_name = $1
+            and ccu.table_name = $2
+        limit 1
+        "#,
+    )
+    .bind(child_table)
+    .bind(parent_table)
+    .fetch_optional(conn)
+    .await?
+    .ok_or_else(|| {
+        anyhow::anyhow!(
+            "no foreign key found from `{child_table}` to `{parent_table}`; add an explicit selector instead"
+        )
+    })?;
+    Ok((row.get("fk_column"), row.get("referenced_column")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::{CompareOp, Expr, Operand};
+
+    fn column(name: &str) -> ColumnInfo {
+        ColumnInfo { name: name.to_string(), data_type: "text".to_string() }
+    }
+
+    #[test]
+    fn test_build_where_order_limit_id() {
+        let columns = [column("id")];
+        let (conditions, order_by, limit, binds) =
+            build_where_order_limit("org", &columns, &[Selector::Id("123".to_string())], &[None]).unwrap();
+        assert_eq!(conditions, vec![r#""id"::text = $1"#]);
+        assert_eq!(order_by, None);
+        assert_eq!(limit, None);
+        assert_eq!(binds, vec![Bind::Scalar("123".to_string())]);
+    }
+
+    #[test]
+    fn test_build_where_order_limit_rand_sets_order_and_limit() {
+        let columns = [column("id")];
+        let (conditions, order_by, limit, _) = build_where_order_limit("org", &columns, &[Selector::Rand(5)], &[None]).unwrap();
+        assert!(conditions.is_empty());
+        assert_eq!(order_by, Some("random()".to_string()));
+        assert_eq!(limit, Some(5));
+    }
+
+    #[test]
+    fn test_build_where_order_limit_sort_validates_column() {
+        let columns = [column("id")];
+        let err = build_where_order_limit("org", &columns, &[Selector::Sort("bogus".to_string(), false)], &[None]).unwrap_err();
+        assert!(err.to_string().contains("bogus"));
+    }
+
+    #[test]
+    fn test_build_where_order_limit_sort_known_column() {
+        let columns = [column("created_at")];
+        let (_, order_by, _, _) =
+            build_where_order_limit("org", &columns, &[Selector::Sort("created_at".to_string(), true)], &[None]).unwrap();
+        assert_eq!(order_by, Some(r#""created_at" desc"#.to_string()));
+    }
+
+    #[test]
+    fn test_build_where_order_limit_expr_binds_numeric_operand() {
+        let columns = [column("id")];
+        let expr = Expr::Compare { column: "age".to_string(), op: CompareOp::Gt, value: Operand::Number("5".to_string()) };
+        let (conditions, _, _, binds) = build_where_order_limit("org", &columns, &[Selector::Expr(expr)], &[None]).unwrap();
+        assert_eq!(conditions, vec![r#""age" > $1"#]);
+        assert_eq!(binds, vec![Bind::Scalar("5".to_string())]);
+    }
+
+    #[test]
+    fn test_build_where_order_limit_foreign_key_with_ids() {
+        let columns = [column("id")];
+        let resolved = Some(("org_id".to_string(), vec!["1".to_string(), "2".to_string()]));
+        let (conditions, _, _, binds) = build_where_order_limit("deduction", &columns, &[Selector::ForeignKey(0)], &[resolved]).unwrap();
+        assert_eq!(conditions, vec![r#""org_id"::text = any($1)"#]);
+        assert_eq!(binds, vec![Bind::Array(vec!["1".to_string(), "2".to_string()])]);
+    }
+
+    #[test]
+    fn test_build_where_order_limit_foreign_key_with_no_parent_rows_is_always_false() {
+        let columns = [column("id")];
+        let resolved = Some(("org_id".to_string(), Vec::new()));
+        let (conditions, _, _, binds) = build_where_order_limit("deduction", &columns, &[Selector::ForeignKey(0)], &[resolved]).unwrap();
+        assert_eq!(conditions, vec!["false".to_string()]);
+        assert!(binds.is_empty());
+    }
+
+    #[test]
+    fn test_sanitize_expr_builtins() {
+        assert_eq!(sanitize_expr("password", "null"), "null");
+        assert_eq!(sanitize_expr("email", "hash"), r#"md5("email"::text)"#);
+        assert_eq!(sanitize_expr("email", "fake_email"), r#"'user_' || "email"::text || '@example.com'"#);
+    }
+
+    #[test]
+    fn test_sanitize_expr_passthrough() {
+        assert_eq!(sanitize_expr("email", "md5(email)"), "md5(email)");
+    }
+
+    #[test]
+    fn test_render_table() {
+        let out = render_table(
+            "org: 2 rows",
+            &["id", "name"],
+            &[vec!["1".to_string(), "acme".to_string()], vec!["20".to_string(), "globex".to_string()]],
+        );
+        assert_eq!(
+            out,
+            "org: 2 rows\n\
+             id | name  \n\
+             ---+-------\n\
+             1  | acme  \n\
+             20 | globex\n"
+        );
+    }
+}
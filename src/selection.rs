@@ -1,4 +1,5 @@
-use crate::parse::{Comma, Parse, ParseError, Punctuated, TokenStream};
+use crate::expr::Expr;
+use crate::parse::{many0, Comma, Parse, ParseError, Punctuated, TokenStream};
 
 // #[derive(Debug, PartialEq)]
 // pub enum Selector {
@@ -24,6 +25,7 @@ pub struct Selection {
 pub struct ParseSelection<'a> {
     table: &'a str,
     selectors: Vec<ParseSelector<'a>>,
+    trailing_error: Option<ParseError>,
 }
 
 
@@ -36,17 +38,26 @@ impl From<ParseSelection<'_>> for Selection {
 }
 
 
+impl<'a> ParseSelection<'a> {
+    // error from the last failed selector attempt within this table's selector list, if any; see
+    // Punctuated::trailing_error, which this just forwards
+    pub fn trailing_error(&self) -> Option<&ParseError> {
+        self.trailing_error.as_ref()
+    }
+}
+
 impl<'a> Parse<'a> for ParseSelection<'a> {
     fn parse(input: &mut TokenStream<'a>) -> Result<ParseSelection<'a>, ParseError> {
         let mut tt = input.clone();
-        let table = tt.next().ok_or(ParseError::new("expected table name"))?;
+        let table = tt.next().ok_or_else(|| ParseError::expected(&tt, "table name"))?;
         let selectors = Punctuated::<Comma, ParseSelector>::parse(&mut tt)?;
+        let trailing_error = selectors.trailing_error().cloned();
         *input = tt;
-        Ok(Self { table, selectors: selectors.into_vec() })
+        Ok(Self { table, selectors: selectors.into_vec(), trailing_error })
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Selector {
     Id(String),
     Rand(usize),
@@ -54,45 +65,74 @@ pub enum Selector {
     Latest(usize),
     // asc = false, desc = true
     Sort(String, bool),
-    Expr,
+    Expr(Expr),
+    // refers to the index of a previous selection in the chain, e.g. org 123 / deduction means
+    // "select all deductions belonging to org 123". Never produced by the parser; the seed
+    // engine synthesizes it for any selection past the first with no explicit `Id` selector.
+    ForeignKey(usize),
+    // never produced by the parser; the seed engine synthesizes it for every selection when --interactive is passed
+    Prompt,
 }
 
-pub struct ParseSelector<'a> {
-    selector: &'a str,
-    args: Vec<&'a str>,
+// either a `keyword arg arg ...` selector (`rand 100`, `sort created_at desc`, a bare id like
+// `123`) or a full comparison/boolean expression. The keyword form is tried first since both share
+// an identifier-ish first token; anything else falls through to the expression grammar.
+pub enum ParseSelector<'a> {
+    Keyword { selector: &'a str, args: Vec<&'a str> },
+    Expr(Expr),
 }
 
 impl<'a> Parse<'a> for ParseSelector<'a> {
     fn parse(input: &mut TokenStream<'a>) -> Result<Self, ParseError> {
         let mut tt = input.clone();
-        let selector = tt.next().ok_or(ParseError::new("expected selector"))?;
+        let selector = tt.peek().ok_or_else(|| ParseError::expected(&tt, "selector"))?;
         if selector == "/" {
-            return Err(ParseError::new("expected selector, got /"));
+            return Err(ParseError::expected(&tt, "selector"));
         }
-        let mut args = Vec::new();
-        while let Some(t) = tt.next_if(|t| t != "," && t != "/") {
-            args.push(t);
+
+        if matches!(selector, "rand" | "latest" | "limit" | "sort") {
+            tt.next();
+            let (args, _) = many0(&mut tt, |s| {
+                s.next_if(|t| t != "," && t != "/").ok_or_else(|| ParseError::expected(s, "argument"))
+            });
+            *input = tt;
+            return Ok(Self::Keyword { selector, args });
+        }
+
+        // A bare value (e.g. `123`) with nothing else before the next boundary is an id.
+        let mut bare = tt.clone();
+        let value = bare.next().unwrap();
+        if bare.peek().map(|t| t == "," || t == "/").unwrap_or(true) {
+            *input = bare;
+            return Ok(Self::Keyword { selector: value, args: Vec::new() });
         }
+
+        let expr = Expr::parse(&mut tt)?;
         *input = tt;
-        Ok(Self { selector, args })
+        Ok(Self::Expr(expr))
     }
 }
 
 impl From<ParseSelector<'_>> for Selector {
     fn from(p: ParseSelector) -> Self {
-        if p.args.is_empty() {
-            Selector::Id(p.selector.to_string())
-        } else if p.selector == "rand" {
-            Selector::Rand(p.args[0].parse().unwrap())
-        } else if p.selector == "latest" {
-            Selector::Latest(p.args[0].parse().unwrap())
-        } else if p.selector == "limit" {
-            Selector::Limit(p.args[0].parse().unwrap())
-        } else if p.selector == "sort" {
-            let direction = p.args.get(1).map(|&s| s == "desc").unwrap_or(false);
-            Selector::Sort(p.args[0].to_string(), direction)
-        } else {
-            Selector::Expr
+        match p {
+            ParseSelector::Expr(expr) => Selector::Expr(expr),
+            ParseSelector::Keyword { selector, args } => {
+                if args.is_empty() {
+                    Selector::Id(selector.to_string())
+                } else if selector == "rand" {
+                    Selector::Rand(args[0].parse().unwrap())
+                } else if selector == "latest" {
+                    Selector::Latest(args[0].parse().unwrap())
+                } else if selector == "limit" {
+                    Selector::Limit(args[0].parse().unwrap())
+                } else if selector == "sort" {
+                    let direction = args.get(1).map(|&s| s == "desc").unwrap_or(false);
+                    Selector::Sort(args[0].to_string(), direction)
+                } else {
+                    unreachable!("a bare selector always has empty args")
+                }
+            }
         }
     }
 }